@@ -0,0 +1,29 @@
+use bitcoin::Network;
+use thiserror::Error;
+
+/// Crate-wide error type. Every fallible function in the transaction-building
+/// path returns this instead of a bare `bitcoincore_rpc::Error` or an
+/// `.unwrap()` panic, so callers get an actionable reason for the failure.
+#[derive(Debug, Error)]
+pub enum TxChainError {
+    #[error("RPC call failed: {0}")]
+    Rpc(#[from] bitcoincore_rpc::Error),
+
+    #[error("encoding error: {0}")]
+    Encoding(String),
+
+    #[error("signing error: {0}")]
+    Signing(String),
+
+    #[error("transaction {txid} is already confirmed: {context}")]
+    AlreadyConfirmed { txid: String, context: String },
+
+    #[error("failed to parse {what}: {reason}")]
+    Parse { what: String, reason: String },
+
+    #[error("insufficient funds: need {needed} but only {available} is spendable")]
+    InsufficientFunds { needed: String, available: String },
+
+    #[error("network mismatch: expected {expected:?}, got address for {actual:?}")]
+    NetworkMismatch { expected: Network, actual: Network },
+}
@@ -0,0 +1,69 @@
+use crate::error::TxChainError;
+use bitcoin::Transaction;
+use bitcoincore_rpc::{Client as RpcClient, RpcApi};
+use log::{debug, warn};
+
+/// Confirmation-target tiers for fee estimation, mirroring Bitcoin Core's
+/// `estimatesmartfee` confirmation-target argument.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfirmationTarget {
+    /// Willing to wait a long time to confirm; lowest fee rate.
+    Background,
+    /// Default priority.
+    Normal,
+    /// Wants to confirm as soon as possible.
+    HighPriority,
+}
+
+impl ConfirmationTarget {
+    fn conf_target_blocks(self) -> u16 {
+        match self {
+            ConfirmationTarget::Background => 72,
+            ConfirmationTarget::Normal => 12,
+            ConfirmationTarget::HighPriority => 1,
+        }
+    }
+}
+
+/// Used when the node has no fee estimate at all (e.g. an empty regtest mempool).
+const FALLBACK_FEE_RATE_SAT_PER_VB: f64 = 1.0;
+
+/// A rough flat fee used only to size a coin selection before the
+/// transaction (and therefore its real vsize) exists; the real fee is
+/// computed afterwards with `compute_fee` and can be higher than this.
+pub const FLAT_FEE_ESTIMATE_SATS: u64 = 500;
+
+/// Asks the node for a sat/vB fee rate for the given confirmation target,
+/// falling back to the mempool minimum fee (and ultimately a fixed floor)
+/// when the node can't produce an estimate, which is the common case on a
+/// freshly started regtest node.
+pub fn estimate_fee_rate(
+    rpc: &RpcClient,
+    target: ConfirmationTarget,
+) -> Result<f64, TxChainError> {
+    let estimate = rpc.estimate_smart_fee(target.conf_target_blocks(), None)?;
+
+    if let Some(fee_rate) = estimate.fee_rate {
+        let sat_per_vbyte = fee_rate.to_sat() as f64 / 1000.0;
+        debug!(
+            "Node estimated {:.3} sat/vB for target {:?}",
+            sat_per_vbyte, target
+        );
+        return Ok(sat_per_vbyte);
+    }
+
+    warn!(
+        "Node returned no fee estimate for target {:?} ({:?}), falling back to mempool minimum",
+        target, estimate.errors
+    );
+    let mempool_min_sat_per_vbyte =
+        rpc.get_mempool_info()?.mempool_min_fee.to_sat() as f64 / 1000.0;
+
+    Ok(mempool_min_sat_per_vbyte.max(FALLBACK_FEE_RATE_SAT_PER_VB))
+}
+
+/// Computes the absolute fee, in satoshis, for `tx` at `rate_sat_per_vbyte`.
+/// `tx` should already carry its final scriptSig/witness so `vsize()` is accurate.
+pub fn compute_fee(rate_sat_per_vbyte: f64, tx: &Transaction) -> u64 {
+    (rate_sat_per_vbyte * tx.vsize() as f64).ceil() as u64
+}
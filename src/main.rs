@@ -1,28 +1,47 @@
 use bitcoin::consensus::encode::serialize;
-use bitcoin::key::{PrivateKey, PublicKey, Secp256k1};
+use bitcoin::key::{PrivateKey, PublicKey, Secp256k1, TapTweak};
+use bitcoin::sighash::{Prevouts, TapSighashType};
 use bitcoin::{
     Address, Amount, Network, OutPoint, ScriptBuf, Sequence, Transaction, TxIn, TxOut, Witness,
 };
-use bitcoincore_rpc::{Auth, Client as RpcClient, RpcApi};
+use bitcoincore_rpc::{Client as RpcClient, RpcApi};
 use env_logger::Builder;
 use log::{debug, error, info, warn, LevelFilter};
-use secp256k1::{rand, SecretKey};
+use secp256k1::{rand, Keypair, SecretKey, XOnlyPublicKey};
 use std::io::Write;
 use std::str::FromStr;
 use std::{thread, time};
 
+mod error;
+use error::TxChainError;
+mod fee;
+use fee::ConfirmationTarget;
+mod utxo;
+use utxo::SelectionStrategy;
+mod network;
+mod metadata;
+mod rbf;
+
+/// Bundles the RPC client and P2PKH signing material that's threaded through
+/// nearly every coin-selecting transaction function, so each one takes a
+/// single reference instead of a long parameter list.
+struct WalletContext<'a> {
+    rpc: &'a RpcClient,
+    private_key: &'a PrivateKey,
+    public_key: &'a PublicKey,
+    secp: &'a Secp256k1<bitcoin::secp256k1::All>,
+}
+
 fn main() {
     init_logger();
-    
+
     info!("Bitcoin Transaction Chain Demonstration");
     info!("---------------------------------------");
-    
-    // Connect to Bitcoin node
+
+    // Connect to Bitcoin node, auto-detecting cookie-file auth if the node's
+    // data directory is reachable, falling back to the regtest default creds.
     let rpc_url = "http://localhost:18443";
-    let client = match RpcClient::new(
-        rpc_url,
-        Auth::UserPass("alice".to_string(), "password".to_string()),
-    ) {
+    let client = match RpcClient::new(rpc_url, network::detect_rpc_auth()) {
         Ok(client) => {
             info!("Connected to Bitcoin node at {}", rpc_url);
             client
@@ -38,18 +57,28 @@ fn main() {
         return;
     }
 
+    // Detect which chain the node is running so we never hardcode regtest
+    let network = match network::detect_network(&client) {
+        Ok(network) => network,
+        Err(e) => {
+            error!("Failed to detect network: {}", e);
+            return;
+        }
+    };
+    network::require_generation_allowed(network);
+
     // Create key pair for our transactions
     info!("Generating key pair for transactions...");
     let secp = Secp256k1::new();
     let secret_key = SecretKey::new(&mut rand::thread_rng());
-    let private_key = PrivateKey::new(secret_key, Network::Regtest);
+    let private_key = PrivateKey::new(secret_key, network);
     let public_key = PublicKey::from_private_key(&secp, &private_key);
 
-    let address = Address::p2pkh(&public_key, Network::Regtest);
+    let address = Address::p2pkh(&public_key, network);
     info!("Generated address: {}", address);
 
     // Ensure we have enough blocks to generate mature coins
-    match ensure_blocks_mined(&client) {
+    match ensure_blocks_mined(&client, network) {
         Ok(_) => debug!("Blocks mined successfully"),
         Err(e) => {
             error!("Failed to mine initial blocks: {}", e);
@@ -73,14 +102,17 @@ fn main() {
     // Mining additional blocks to mature the coinbase
     info!("Mining 100 additional blocks to mature the coinbase...");
     match client.get_new_address(None, None) {
-        Ok(temp_address) => {
-            let address = temp_address.require_network(Network::Regtest).unwrap();
-            match client.generate_to_address(100, &address) {
+        Ok(temp_address) => match temp_address.require_network(network) {
+            Ok(address) => match client.generate_to_address(100, &address) {
                 Ok(_) => info!("Successfully mined 100 blocks"),
                 Err(e) => {
                     error!("Failed to mine additional blocks: {}", e);
                     return;
                 }
+            },
+            Err(e) => {
+                error!("Temporary address didn't match detected network: {}", e);
+                return;
             }
         },
         Err(e) => {
@@ -95,7 +127,7 @@ fn main() {
 
     // Create and submit first transaction (spending coinbase)
     info!("Creating first transaction to spend the coinbase output...");
-    let first_tx = match create_first_transaction(&client, &coinbase_txid, &private_key, &public_key, &secp) {
+    let first_tx = match create_first_transaction(&client, &coinbase_txid, &private_key, &public_key, &secp, network) {
         Ok(txid) => {
             info!("First transaction created and submitted: {}", txid);
             txid
@@ -108,7 +140,7 @@ fn main() {
 
     // Generate a block to confirm first transaction
     info!("Mining a block to confirm the first transaction...");
-    match generate_block(&client) {
+    match generate_block(&client, network) {
         Ok(block_hash) => debug!("Generated block {} to confirm first transaction", block_hash),
         Err(e) => {
             error!("Failed to generate block: {}", e);
@@ -122,7 +154,7 @@ fn main() {
 
     // Create and submit second transaction (spending first tx)
     info!("Creating second transaction to spend the output of the first transaction...");
-    let second_tx = match create_second_transaction(&client, &first_tx, &private_key, &public_key, &secp) {
+    let second_tx = match create_second_transaction(&client, &first_tx, &private_key, &public_key, &secp, network) {
         Ok(txid) => {
             info!("Second transaction created and submitted: {}", txid);
             txid
@@ -135,7 +167,7 @@ fn main() {
 
     // Generate a final block to confirm second transaction
     info!("Mining a block to confirm the second transaction...");
-    match generate_block(&client) {
+    match generate_block(&client, network) {
         Ok(block_hash) => debug!("Generated block {} to confirm second transaction", block_hash),
         Err(e) => {
             error!("Failed to generate final block: {}", e);
@@ -148,12 +180,206 @@ fn main() {
     info!("1. Coinbase transaction: {}", coinbase_txid);
     info!("2. First transaction: {}", first_tx);
     info!("3. Second transaction: {}", second_tx);
+
+    // Demonstrate the native segwit (P2WPKH) path on top of the same chain
+    info!("Creating a segwit (P2WPKH) transaction to spend the second transaction's output...");
+    match create_first_transaction_segwit(&client, &second_tx, &private_key, &public_key, &secp, network) {
+        Ok(segwit_txid) => {
+            info!("Segwit transaction created and submitted: {}", segwit_txid);
+            if let Err(e) = generate_block(&client, network) {
+                error!("Failed to generate block: {}", e);
+                return;
+            }
+            thread::sleep(time::Duration::from_secs(1));
+
+            info!("Creating a second segwit transaction to spend that output...");
+            match create_second_transaction_segwit(&client, &segwit_txid, &private_key, &public_key, &secp, network) {
+                Ok(second_segwit_txid) => {
+                    info!("Second segwit transaction created and submitted: {}", second_segwit_txid);
+                    if let Err(e) = generate_block(&client, network) {
+                        error!("Failed to generate final block: {}", e);
+                    }
+                }
+                Err(e) => error!("Failed to create second segwit transaction: {}", e),
+            }
+        }
+        Err(e) => error!("Failed to create segwit transaction: {}", e),
+    }
+
+    // Demonstrate the Taproot (P2TR) key-path path
+    info!("Generating a key pair for taproot transactions...");
+    let taproot_keypair = Keypair::new(&secp, &mut rand::thread_rng());
+    let taproot_address = p2tr_address(&secp, &taproot_keypair, network);
+    info!("Generated taproot address: {}", taproot_address);
+
+    info!("Mining a block to fund the taproot address...");
+    let taproot_coinbase_txid = match mine_block(&client, &taproot_address) {
+        Ok(txid) => txid,
+        Err(e) => {
+            error!("Failed to mine block for taproot funding: {}", e);
+            return;
+        }
+    };
+    let temp_address = match client.get_new_address(None, None) {
+        Ok(a) => match a.require_network(network) {
+            Ok(a) => a,
+            Err(e) => {
+                error!("Temporary address didn't match detected network: {}", e);
+                return;
+            }
+        },
+        Err(e) => {
+            error!("Failed to get a temporary address: {}", e);
+            return;
+        }
+    };
+    if let Err(e) = client.generate_to_address(100, &temp_address) {
+        error!("Failed to mature taproot coinbase: {}", e);
+        return;
+    }
+    thread::sleep(time::Duration::from_secs(1));
+
+    info!("Creating a taproot transaction to spend the coinbase output...");
+    match create_first_transaction_taproot(&client, &taproot_coinbase_txid, &taproot_keypair, &secp, network) {
+        Ok(taproot_txid) => {
+            info!("Taproot transaction created and submitted: {}", taproot_txid);
+            if let Err(e) = generate_block(&client, network) {
+                error!("Failed to generate block: {}", e);
+                return;
+            }
+            thread::sleep(time::Duration::from_secs(1));
+
+            info!("Creating a second taproot transaction to spend that output...");
+            match create_second_transaction_taproot(&client, &taproot_txid, &taproot_keypair, &secp, network) {
+                Ok(second_taproot_txid) => {
+                    info!("Second taproot transaction created and submitted: {}", second_taproot_txid);
+                    if let Err(e) = generate_block(&client, network) {
+                        error!("Failed to generate final block: {}", e);
+                    }
+                }
+                Err(e) => error!("Failed to create second taproot transaction: {}", e),
+            }
+        }
+        Err(e) => error!("Failed to create taproot transaction: {}", e),
+    }
+
+    // Demonstrate the reusable, coin-selecting send API
+    info!("Mining a block to accumulate another spendable utxo at the original address...");
+    if let Err(e) = client.generate_to_address(1, &address) {
+        error!("Failed to mine block: {}", e);
+        return;
+    }
+
+    // That utxo is a coinbase output, so it needs to mature before
+    // list_spendable_utxos will consider it spendable (see utxo::COINBASE_MATURITY).
+    info!("Mining 100 additional blocks to mature that coinbase...");
+    let temp_address = match client.get_new_address(None, None) {
+        Ok(a) => match require_network_checked(a, network) {
+            Ok(a) => a,
+            Err(e) => {
+                error!("Temporary address didn't match detected network: {}", e);
+                return;
+            }
+        },
+        Err(e) => {
+            error!("Failed to get a temporary address: {}", e);
+            return;
+        }
+    };
+    if let Err(e) = client.generate_to_address(100, &temp_address) {
+        error!("Failed to mature coinbase for the coin-selecting sender: {}", e);
+        return;
+    }
+    thread::sleep(time::Duration::from_secs(1));
+
+    let wallet_ctx = WalletContext {
+        rpc: &client,
+        private_key: &private_key,
+        public_key: &public_key,
+        secp: &secp,
+    };
+
+    let send_target = Amount::from_sat(5_000);
+    info!("Sending {} via the coin-selecting sender (RBF-enabled)...", send_target);
+    match send_amount(
+        &wallet_ctx,
+        &address,
+        &taproot_address,
+        send_target,
+        SelectionStrategy::BranchAndBound,
+        Some(b"demo-deposit"),
+        true,
+    ) {
+        Ok(txid) => {
+            info!("Coin-selected transaction submitted: {}", txid);
+
+            // Demonstrate bumping the fee on the still-unconfirmed transaction
+            // via a BIP125 replacement before it ever confirms.
+            info!("Replacing it with a higher-fee version before it confirms...");
+            match bump_fee_rbf(
+                &wallet_ctx,
+                &txid,
+                &address,
+                &taproot_address,
+                send_target,
+                Some(b"demo-deposit"),
+                ConfirmationTarget::HighPriority,
+            ) {
+                Ok(replacement_txid) => {
+                    info!("Replacement transaction submitted: {}", replacement_txid);
+
+                    // Demonstrate CPFP by spending the replacement's own change
+                    // output back to itself, as if the parent's fee were stuck too low.
+                    info!("Spending its change output as a CPFP child...");
+                    match cpfp_bump(
+                        &wallet_ctx,
+                        &replacement_txid,
+                        1,
+                        &address,
+                        ConfirmationTarget::HighPriority,
+                    ) {
+                        Ok(child_txid) => info!("CPFP child transaction submitted: {}", child_txid),
+                        Err(e) => warn!("CPFP bump skipped: {}", e),
+                    }
+                }
+                Err(e) => warn!("RBF replacement skipped: {}", e),
+            }
+        }
+        Err(e) => warn!(
+            "Coin-selected send skipped (likely no mature utxos yet): {}",
+            e
+        ),
+    }
+
+    // Demonstrate the low-priority fee tier and the largest-first selection
+    // strategy, which the RBF/CPFP demo above doesn't otherwise exercise.
+    match fee::estimate_fee_rate(&client, ConfirmationTarget::Background) {
+        Ok(rate) => info!("Background-priority fee estimate: {:.3} sat/vB", rate),
+        Err(e) => warn!("Background fee estimate unavailable: {}", e),
+    }
+    let dust_sweep_target = Amount::from_sat(1_000);
+    info!(
+        "Sending {} via the largest-first sender (no RBF)...",
+        dust_sweep_target
+    );
+    match send_amount(
+        &wallet_ctx,
+        &address,
+        &taproot_address,
+        dust_sweep_target,
+        SelectionStrategy::LargestFirst,
+        None,
+        false,
+    ) {
+        Ok(txid) => info!("Largest-first transaction submitted: {}", txid),
+        Err(e) => warn!("Largest-first send skipped: {}", e),
+    }
 }
 
 fn init_logger() {
     use colored::*;
     let mut builder = Builder::new();
-    
+
     builder
         .format(|buf, record| {
             let timestamp = chrono::Local::now().format("%Y-%m-%d %H:%M:%S%.3f");
@@ -164,7 +390,7 @@ fn init_logger() {
                 log::Level::Debug => record.level().to_string().blue().bold(),
                 log::Level::Trace => record.level().to_string().magenta().bold(),
             };
-            
+
             let message = match record.level() {
                 log::Level::Error => record.args().to_string().red(),
                 log::Level::Warn => record.args().to_string().yellow(),
@@ -172,7 +398,7 @@ fn init_logger() {
                 log::Level::Debug => record.args().to_string().bright_black(),
                 log::Level::Trace => record.args().to_string().magenta(),
             };
-            
+
             writeln!(
                 buf,
                 "[{}] [{}] {}",
@@ -185,9 +411,9 @@ fn init_logger() {
         .init();
 }
 
-fn initialize_wallet(client: &RpcClient) -> Result<(), bitcoincore_rpc::Error> {
+fn initialize_wallet(client: &RpcClient) -> Result<(), TxChainError> {
     info!("Initializing wallet...");
-    
+
     // Try to create the wallet first
     match client.create_wallet("mywallet", None, None, None, None) {
         Ok(_) => {
@@ -216,7 +442,7 @@ fn initialize_wallet(client: &RpcClient) -> Result<(), bitcoincore_rpc::Error> {
                                 info!("Wallet 'mywallet' is in the list of wallets");
                                 return Ok(());
                             }
-                            return Err(load_err);
+                            return Err(load_err.into());
                         }
                     }
                 }
@@ -228,13 +454,36 @@ fn initialize_wallet(client: &RpcClient) -> Result<(), bitcoincore_rpc::Error> {
                     info!("Wallet 'mywallet' is in the list of wallets");
                     return Ok(());
                 }
-                return Err(e);
+                return Err(e.into());
             }
         }
     }
 }
 
-fn ensure_blocks_mined(rpc: &RpcClient) -> Result<(), bitcoincore_rpc::Error> {
+/// Checks that `address_uncheck` is actually valid on `network` before
+/// trusting it, rather than a bare `.require_network()` whose failure just
+/// gets flattened into a generic parse error. The node is only ever asked
+/// for addresses on the network we already detected, so a mismatch here
+/// means the node is misconfigured or pointed at the wrong network.
+fn require_network_checked(
+    address_uncheck: bitcoin::Address<bitcoin::address::NetworkUnchecked>,
+    network: Network,
+) -> Result<Address, TxChainError> {
+    if address_uncheck.is_valid_for_network(network) {
+        return Ok(address_uncheck.assume_checked());
+    }
+
+    let actual = [Network::Bitcoin, Network::Testnet, Network::Signet, Network::Regtest]
+        .into_iter()
+        .find(|&candidate| address_uncheck.is_valid_for_network(candidate))
+        .unwrap_or(network);
+    Err(TxChainError::NetworkMismatch {
+        expected: network,
+        actual,
+    })
+}
+
+fn ensure_blocks_mined(rpc: &RpcClient, network: Network) -> Result<(), TxChainError> {
     let block_count = rpc.get_block_count()?;
     debug!("Current block count: {}", block_count);
 
@@ -242,7 +491,7 @@ fn ensure_blocks_mined(rpc: &RpcClient) -> Result<(), bitcoincore_rpc::Error> {
         info!("Mining initial blocks to ensure we have mature coins...");
         // Get a new address for mining rewards
         let address_uncheck = rpc.get_new_address(None, None)?;
-        let address = address_uncheck.require_network(Network::Regtest).unwrap();
+        let address = require_network_checked(address_uncheck, network)?;
         // Mine enough blocks to reach at least 101
         let blocks_needed = 101 - block_count;
         rpc.generate_to_address(blocks_needed, &address)?;
@@ -254,7 +503,7 @@ fn ensure_blocks_mined(rpc: &RpcClient) -> Result<(), bitcoincore_rpc::Error> {
     Ok(())
 }
 
-fn mine_block(rpc: &RpcClient, address: &Address) -> Result<String, bitcoincore_rpc::Error> {
+fn mine_block(rpc: &RpcClient, address: &Address) -> Result<String, TxChainError> {
     // Create a coinbase transaction paying to our address
     let block_hashes = rpc.generate_to_address(1, address)?;
     let block_hash = &block_hashes[0];
@@ -268,24 +517,58 @@ fn mine_block(rpc: &RpcClient, address: &Address) -> Result<String, bitcoincore_
     Ok(coinbase_txid)
 }
 
-fn generate_block(rpc: &RpcClient) -> Result<String, bitcoincore_rpc::Error> {
+fn generate_block(rpc: &RpcClient, network: Network) -> Result<String, TxChainError> {
     // Get an address to generate to
     let address_uncheck = rpc.get_new_address(None, None)?;
-    let address = address_uncheck.require_network(Network::Regtest).unwrap();
+    let address = require_network_checked(address_uncheck, network)?;
     let block_hashes = rpc.generate_to_address(1, &address)?;
     debug!("Generated block: {}", block_hashes[0]);
     Ok(block_hashes[0].to_string())
 }
 
+/// Parses a txid string, wrapping the error with what we were trying to parse
+/// since callers only have a bare string at the point of failure.
+fn parse_txid(txid: &str) -> Result<bitcoin::Txid, TxChainError> {
+    bitcoin::Txid::from_str(txid).map_err(|e| TxChainError::Parse {
+        what: "txid".to_string(),
+        reason: e.to_string(),
+    })
+}
+
+/// Computes the BIP143-less legacy sighash message for `input_index`.
+fn legacy_sighash_message(
+    tx: &Transaction,
+    input_index: usize,
+    script_code: &ScriptBuf,
+) -> Result<bitcoin::secp256k1::Message, TxChainError> {
+    let sighash = bitcoin::sighash::SighashCache::new(tx)
+        .legacy_signature_hash(
+            input_index,
+            script_code,
+            bitcoin::sighash::EcdsaSighashType::All.to_u32(),
+        )
+        .map_err(|e| TxChainError::Signing(e.to_string()))?;
+    bitcoin::secp256k1::Message::from_digest_slice(&sighash[..])
+        .map_err(|e| TxChainError::Signing(e.to_string()))
+}
+
+fn push_bytes(data: &[u8]) -> Result<bitcoin::script::PushBytesBuf, TxChainError> {
+    let mut buf = bitcoin::script::PushBytesBuf::with_capacity(data.len());
+    buf.extend_from_slice(data)
+        .map_err(|e| TxChainError::Encoding(e.to_string()))?;
+    Ok(buf)
+}
+
 fn create_first_transaction(
     rpc: &RpcClient,
     coinbase_txid: &str,
     private_key: &PrivateKey,
     public_key: &PublicKey,
     secp: &Secp256k1<bitcoin::secp256k1::All>,
-) -> Result<String, bitcoincore_rpc::Error> {
+    network: Network,
+) -> Result<String, TxChainError> {
     // Get coinbase transaction details
-    let txid = bitcoin::Txid::from_str(coinbase_txid).unwrap();
+    let txid = parse_txid(coinbase_txid)?;
     let tx_info = rpc.get_raw_transaction_info(&txid, None)?;
 
     // Get value from the first output (assuming coinbase has only one output to our address)
@@ -302,49 +585,50 @@ fn create_first_transaction(
         witness: Witness::new(),
     };
 
-    // Create output (sending to the same address, but with a slightly smaller amount for fees)
-    let fee = 1000; // 1000 satoshis fee
-    let txout = TxOut {
-        value: Amount::from_sat(value_sats - fee),
-        script_pubkey: address_to_script(public_key, Network::Regtest),
-    };
-    debug!("Creating transaction with fee: {} satoshis", fee);
-
-    // Create unsigned transaction
-    let mut tx = Transaction {
-        version: bitcoin::transaction::Version(2),
-        lock_time: bitcoin::locktime::absolute::LockTime::ZERO,
-        input: vec![txin],
-        output: vec![txout],
-    };
+    let script_code = address_to_script(public_key, network);
 
-    // Sign the transaction
-    debug!("Signing transaction...");
-    let script_code = address_to_script(public_key, Network::Regtest);
-    let sighash = bitcoin::sighash::SighashCache::new(&tx)
-        .legacy_signature_hash(
-            0,
-            &script_code,
-            bitcoin::sighash::EcdsaSighashType::All.to_u32(),
-        )
-        .unwrap();
+    // Sign with a placeholder fee first so the transaction has its final
+    // scriptSig size and `vsize()` is accurate, then recompute the real fee
+    // from that size and re-sign with the corrected output value.
+    let build_and_sign = |fee: u64| -> Result<Transaction, TxChainError> {
+        let txout = TxOut {
+            value: Amount::from_sat(value_sats - fee),
+            script_pubkey: script_code.clone(),
+        };
+        let mut tx = Transaction {
+            version: bitcoin::transaction::Version(2),
+            lock_time: bitcoin::locktime::absolute::LockTime::ZERO,
+            input: vec![txin.clone()],
+            output: vec![txout],
+        };
+
+        let message = legacy_sighash_message(&tx, 0, &script_code)?;
+        let signature = secp.sign_ecdsa(&message, &private_key.inner);
+
+        let mut sig_ser = signature.serialize_der().to_vec();
+        sig_ser.push(bitcoin::sighash::EcdsaSighashType::All.to_u32() as u8);
 
-    let message = bitcoin::secp256k1::Message::from_digest_slice(&sighash[..]).unwrap();
-    let signature = secp.sign_ecdsa(&message, &private_key.inner);
+        let script_sig = ScriptBuf::builder()
+            .push_slice(push_bytes(&sig_ser)?)
+            .push_key(public_key)
+            .into_script();
 
-    let mut sig_ser = signature.serialize_der().to_vec();
-    sig_ser.push(bitcoin::sighash::EcdsaSighashType::All.to_u32() as u8);
+        tx.input[0].script_sig = script_sig;
+        Ok(tx)
+    };
 
-    // Create a proper PushBytesBuf
-    let mut push_bytes_buf = bitcoin::script::PushBytesBuf::with_capacity(sig_ser.len());
-    push_bytes_buf.extend_from_slice(&sig_ser).unwrap();
+    debug!("Signing dummy transaction to determine accurate size...");
+    let dummy_tx = build_and_sign(0)?;
 
-    let script_sig = ScriptBuf::builder()
-        .push_slice(push_bytes_buf)
-        .push_key(public_key)
-        .into_script();
+    let fee_rate = fee::estimate_fee_rate(rpc, ConfirmationTarget::Normal)?;
+    let final_fee = fee::compute_fee(fee_rate, &dummy_tx);
+    debug!(
+        "Creating transaction with fee: {} satoshis ({:.3} sat/vB)",
+        final_fee, fee_rate
+    );
 
-    tx.input[0].script_sig = script_sig;
+    debug!("Signing transaction...");
+    let tx = build_and_sign(final_fee)?;
 
     // Serialize and submit transaction
     let tx_hex = hex::encode(serialize(&tx));
@@ -363,9 +647,10 @@ fn create_second_transaction(
     private_key: &PrivateKey,
     public_key: &PublicKey,
     secp: &Secp256k1<bitcoin::secp256k1::All>,
-) -> Result<String, bitcoincore_rpc::Error> {
+    network: Network,
+) -> Result<String, TxChainError> {
     // Get the first transaction details
-    let txid = bitcoin::Txid::from_str(first_txid).unwrap();
+    let txid = parse_txid(first_txid)?;
     let tx_info = rpc.get_raw_transaction_info(&txid, None)?;
 
     // Get value from the output
@@ -382,49 +667,50 @@ fn create_second_transaction(
         witness: Witness::new(),
     };
 
-    // Create output (sending to the same address, minus fees)
-    let fee = 1000; // 1000 satoshis fee
-    let txout = TxOut {
-        value: Amount::from_sat(value_sats - fee),
-        script_pubkey: address_to_script(public_key, Network::Regtest),
-    };
-    debug!("Creating transaction with fee: {} satoshis", fee);
-
-    // Create unsigned transaction
-    let mut tx = Transaction {
-        version: bitcoin::transaction::Version(2),
-        lock_time: bitcoin::locktime::absolute::LockTime::ZERO,
-        input: vec![txin],
-        output: vec![txout],
-    };
+    let script_code = address_to_script(public_key, network);
 
-    // Sign the transaction
-    debug!("Signing transaction...");
-    let script_code = address_to_script(public_key, Network::Regtest);
-    let sighash = bitcoin::sighash::SighashCache::new(&tx)
-        .legacy_signature_hash(
-            0,
-            &script_code,
-            bitcoin::sighash::EcdsaSighashType::All.to_u32(),
-        )
-        .unwrap();
+    // Sign with a placeholder fee first so the transaction has its final
+    // scriptSig size and `vsize()` is accurate, then recompute the real fee
+    // from that size and re-sign with the corrected output value.
+    let build_and_sign = |fee: u64| -> Result<Transaction, TxChainError> {
+        let txout = TxOut {
+            value: Amount::from_sat(value_sats - fee),
+            script_pubkey: script_code.clone(),
+        };
+        let mut tx = Transaction {
+            version: bitcoin::transaction::Version(2),
+            lock_time: bitcoin::locktime::absolute::LockTime::ZERO,
+            input: vec![txin.clone()],
+            output: vec![txout],
+        };
+
+        let message = legacy_sighash_message(&tx, 0, &script_code)?;
+        let signature = secp.sign_ecdsa(&message, &private_key.inner);
 
-    let message = bitcoin::secp256k1::Message::from_digest_slice(&sighash[..]).unwrap();
-    let signature = secp.sign_ecdsa(&message, &private_key.inner);
+        let mut sig_ser = signature.serialize_der().to_vec();
+        sig_ser.push(bitcoin::sighash::EcdsaSighashType::All.to_u32() as u8);
 
-    let mut sig_ser = signature.serialize_der().to_vec();
-    sig_ser.push(bitcoin::sighash::EcdsaSighashType::All.to_u32() as u8);
+        let script_sig = ScriptBuf::builder()
+            .push_slice(push_bytes(&sig_ser)?)
+            .push_key(public_key)
+            .into_script();
 
-    // Create a proper PushBytesBuf
-    let mut push_bytes_buf = bitcoin::script::PushBytesBuf::with_capacity(sig_ser.len());
-    push_bytes_buf.extend_from_slice(&sig_ser).unwrap();
+        tx.input[0].script_sig = script_sig;
+        Ok(tx)
+    };
+
+    debug!("Signing dummy transaction to determine accurate size...");
+    let dummy_tx = build_and_sign(0)?;
 
-    let script_sig = ScriptBuf::builder()
-        .push_slice(push_bytes_buf)
-        .push_key(public_key)
-        .into_script();
+    let fee_rate = fee::estimate_fee_rate(rpc, ConfirmationTarget::Normal)?;
+    let final_fee = fee::compute_fee(fee_rate, &dummy_tx);
+    debug!(
+        "Creating transaction with fee: {} satoshis ({:.3} sat/vB)",
+        final_fee, fee_rate
+    );
 
-    tx.input[0].script_sig = script_sig;
+    debug!("Signing transaction...");
+    let tx = build_and_sign(final_fee)?;
 
     // Serialize and submit transaction
     let tx_hex = hex::encode(serialize(&tx));
@@ -437,6 +723,815 @@ fn create_second_transaction(
     Ok(txid.to_string())
 }
 
+/// Sends `target_amount` from `from_address` (a P2PKH address controlled by
+/// `private_key`/`public_key`) to `to_address`, selecting whichever UTXOs are
+/// needed to cover it rather than assuming a single known prevout. Adds a
+/// change output back to `from_address` whenever anything is left over after
+/// the target and the real fee, whichever selection strategy was used.
+/// When `metadata` is provided, an extra `OP_RETURN` output tags the
+/// transaction with it (see the `metadata` module). When `enable_rbf` is set,
+/// inputs signal BIP125 replaceability so the transaction can later be fee-bumped
+/// via `bump_fee_rbf`.
+fn send_amount(
+    ctx: &WalletContext,
+    from_address: &Address,
+    to_address: &Address,
+    target_amount: Amount,
+    strategy: SelectionStrategy,
+    metadata: Option<&[u8]>,
+    enable_rbf: bool,
+) -> Result<String, TxChainError> {
+    let op_return_output = metadata.map(metadata::build_op_return_output).transpose()?;
+
+    let available = utxo::list_spendable_utxos(ctx.rpc, from_address)?;
+    debug!(
+        "{} spendable utxo(s) at {} ({} coinbase, {} confirmations on the newest)",
+        available.len(),
+        from_address,
+        available.iter().filter(|u| u.is_coinbase).count(),
+        available.iter().map(|u| u.confirmations).min().unwrap_or(0)
+    );
+
+    // Select against a flat fee estimate first; select_utxos is re-run once
+    // below if the real fee, taken from the signed size, exceeds what it covers.
+    let fee_rate = fee::estimate_fee_rate(ctx.rpc, ConfirmationTarget::Normal)?;
+    let rough_fee = Amount::from_sat(fee::FLAT_FEE_ESTIMATE_SATS);
+
+    let total_available = available
+        .iter()
+        .fold(Amount::from_sat(0), |acc, u| acc + u.value);
+    let mut selection = utxo::select_utxos(&available, target_amount + rough_fee, strategy)
+        .ok_or_else(|| TxChainError::InsufficientFunds {
+            needed: (target_amount + rough_fee).to_string(),
+            available: total_available.to_string(),
+        })?;
+
+    debug!(
+        "Selected {} utxo(s) totaling {} (change: {})",
+        selection.utxos.len(),
+        selection
+            .utxos
+            .iter()
+            .fold(Amount::from_sat(0), |acc, u| acc + u.value),
+        selection.change
+    );
+
+    let input_sequence = if enable_rbf {
+        rbf::RBF_SEQUENCE
+    } else {
+        Sequence::MAX
+    };
+
+    let script_code = from_address.script_pubkey();
+    let build_and_sign = |utxos: &[utxo::Utxo], change: Amount| -> Result<Transaction, TxChainError> {
+        let inputs: Vec<TxIn> = utxos
+            .iter()
+            .map(|utxo| TxIn {
+                previous_output: utxo.outpoint,
+                script_sig: ScriptBuf::new(),
+                sequence: input_sequence,
+                witness: Witness::new(),
+            })
+            .collect();
+
+        let mut outputs = vec![TxOut {
+            value: target_amount,
+            script_pubkey: to_address.script_pubkey(),
+        }];
+        if change > Amount::from_sat(0) {
+            outputs.push(TxOut {
+                value: change,
+                script_pubkey: from_address.script_pubkey(),
+            });
+        }
+        if let Some(op_return_output) = &op_return_output {
+            outputs.push(op_return_output.clone());
+        }
+
+        let mut tx = Transaction {
+            version: bitcoin::transaction::Version(2),
+            lock_time: bitcoin::locktime::absolute::LockTime::ZERO,
+            input: inputs,
+            output: outputs,
+        };
+
+        // Sign each input at its own index
+        for index in 0..tx.input.len() {
+            let message = legacy_sighash_message(&tx, index, &script_code)?;
+            let signature = ctx.secp.sign_ecdsa(&message, &ctx.private_key.inner);
+
+            let mut sig_ser = signature.serialize_der().to_vec();
+            sig_ser.push(bitcoin::sighash::EcdsaSighashType::All.to_u32() as u8);
+
+            tx.input[index].script_sig = ScriptBuf::builder()
+                .push_slice(push_bytes(&sig_ser)?)
+                .push_key(ctx.public_key)
+                .into_script();
+        }
+
+        Ok(tx)
+    };
+
+    debug!("Signing dummy transaction to determine accurate size...");
+    let dummy_tx = build_and_sign(&selection.utxos, selection.change)?;
+    let mut final_fee = fee::compute_fee(fee_rate, &dummy_tx);
+
+    let mut total_selected = selection
+        .utxos
+        .iter()
+        .fold(Amount::from_sat(0), |acc, u| acc + u.value);
+
+    // The rough flat-fee margin used to pick the selection above only covers
+    // a single-input, single-output estimate; a selection needing several
+    // inputs (e.g. many small UTXOs) can have a real fee above that margin.
+    // Reselect against the real fee rather than let the change computation
+    // below underflow.
+    if total_selected < target_amount + Amount::from_sat(final_fee) {
+        debug!(
+            "Flat-fee margin of {} sat was insufficient (real fee {} sat); reselecting",
+            fee::FLAT_FEE_ESTIMATE_SATS,
+            final_fee
+        );
+        selection = utxo::select_utxos(&available, target_amount + Amount::from_sat(final_fee), strategy)
+            .ok_or_else(|| TxChainError::InsufficientFunds {
+                needed: (target_amount + Amount::from_sat(final_fee)).to_string(),
+                available: total_available.to_string(),
+            })?;
+        total_selected = selection
+            .utxos
+            .iter()
+            .fold(Amount::from_sat(0), |acc, u| acc + u.value);
+        let dummy_tx = build_and_sign(&selection.utxos, selection.change)?;
+        final_fee = fee::compute_fee(fee_rate, &dummy_tx);
+    }
+
+    // Re-derive the change amount now that the fee is known precisely; the
+    // rough estimate above only needs to be close enough to pick a selection.
+    if total_selected < target_amount + Amount::from_sat(final_fee) {
+        return Err(TxChainError::InsufficientFunds {
+            needed: (target_amount + Amount::from_sat(final_fee)).to_string(),
+            available: total_selected.to_string(),
+        });
+    }
+    let change = total_selected - target_amount - Amount::from_sat(final_fee);
+    debug!(
+        "Creating transaction with fee: {} satoshis ({:.3} sat/vB), change: {}",
+        final_fee, fee_rate, change
+    );
+
+    let tx = build_and_sign(&selection.utxos, change)?;
+    let tx_hex = hex::encode(serialize(&tx));
+    debug!("Transaction serialized, submitting to network...");
+
+    let txid = ctx.rpc.send_raw_transaction(tx_hex)?;
+    debug!("Transaction submitted successfully");
+
+    Ok(txid.to_string())
+}
+
 fn address_to_script(public_key: &PublicKey, network: Network) -> ScriptBuf {
     Address::p2pkh(public_key, network).script_pubkey()
-}
\ No newline at end of file
+}
+
+fn compressed_pubkey(public_key: &PublicKey) -> Result<bitcoin::CompressedPublicKey, TxChainError> {
+    bitcoin::CompressedPublicKey::try_from(*public_key).map_err(|e| TxChainError::Parse {
+        what: "compressed public key".to_string(),
+        reason: e.to_string(),
+    })
+}
+
+/// Builds the P2WPKH scriptPubkey (the witness program, `OP_0 <20-byte-hash>`)
+/// for `public_key`. This is the value `p2wpkh_signature_hash` expects as its
+/// `script_pubkey` argument — it derives the BIP143 "script code"
+/// (`OP_DUP OP_HASH160 ... OP_EQUALVERIFY OP_CHECKSIG`) from this internally.
+fn p2wpkh_script_pubkey(
+    public_key: &PublicKey,
+    network: Network,
+) -> Result<ScriptBuf, TxChainError> {
+    let address = Address::p2wpkh(&compressed_pubkey(public_key)?, network);
+    Ok(address.script_pubkey())
+}
+
+fn create_first_transaction_segwit(
+    rpc: &RpcClient,
+    coinbase_txid: &str,
+    private_key: &PrivateKey,
+    public_key: &PublicKey,
+    secp: &Secp256k1<bitcoin::secp256k1::All>,
+    network: Network,
+) -> Result<String, TxChainError> {
+    // Get coinbase transaction details
+    let txid = parse_txid(coinbase_txid)?;
+    let tx_info = rpc.get_raw_transaction_info(&txid, None)?;
+
+    // Get value from the first output (assuming coinbase has only one output to our address)
+    let value_sats = tx_info.vout[0].value.to_sat();
+    let vout_idx = 0; // Usually coinbase has just one output
+    debug!("Coinbase output value: {} satoshis", value_sats);
+
+    // Create input from coinbase
+    let outpoint = OutPoint::new(txid, vout_idx);
+    let txin = TxIn {
+        previous_output: outpoint,
+        script_sig: ScriptBuf::new(),
+        sequence: Sequence::MAX,
+        witness: Witness::new(),
+    };
+
+    // Create a native segwit output (sending to the same key, but as P2WPKH)
+    let dest_address = Address::p2wpkh(&compressed_pubkey(public_key)?, network);
+    let script_code = p2wpkh_script_pubkey(public_key, network)?;
+    let prevout_value = Amount::from_sat(value_sats);
+
+    // Sign with a placeholder fee first so the transaction has its final
+    // witness size and `vsize()` is accurate, then recompute the real fee
+    // from that size and re-sign with the corrected output value.
+    let build_and_sign = |fee: u64| -> Result<Transaction, TxChainError> {
+        let txout = TxOut {
+            value: Amount::from_sat(value_sats - fee),
+            script_pubkey: dest_address.script_pubkey(),
+        };
+        let mut tx = Transaction {
+            version: bitcoin::transaction::Version(2),
+            lock_time: bitcoin::locktime::absolute::LockTime::ZERO,
+            input: vec![txin.clone()],
+            output: vec![txout],
+        };
+
+        // Sign the input using the BIP143 segwit sighash algorithm
+        let sighash = bitcoin::sighash::SighashCache::new(&tx)
+            .p2wpkh_signature_hash(
+                0,
+                &script_code,
+                prevout_value,
+                bitcoin::sighash::EcdsaSighashType::All,
+            )
+            .map_err(|e| TxChainError::Signing(e.to_string()))?;
+
+        let message = bitcoin::secp256k1::Message::from_digest_slice(&sighash[..])
+            .map_err(|e| TxChainError::Signing(e.to_string()))?;
+        let signature = secp.sign_ecdsa(&message, &private_key.inner);
+
+        let mut sig_ser = signature.serialize_der().to_vec();
+        sig_ser.push(bitcoin::sighash::EcdsaSighashType::All.to_u32() as u8);
+
+        // The signature and pubkey go into the witness; scriptSig stays empty
+        let mut witness = Witness::new();
+        witness.push(sig_ser);
+        witness.push(public_key.to_bytes());
+        tx.input[0].witness = witness;
+
+        Ok(tx)
+    };
+
+    debug!("Signing dummy transaction to determine accurate size...");
+    let dummy_tx = build_and_sign(0)?;
+
+    let fee_rate = fee::estimate_fee_rate(rpc, ConfirmationTarget::Normal)?;
+    let final_fee = fee::compute_fee(fee_rate, &dummy_tx);
+    debug!(
+        "Creating segwit transaction with fee: {} satoshis ({:.3} sat/vB)",
+        final_fee, fee_rate
+    );
+
+    debug!("Signing segwit transaction...");
+    let tx = build_and_sign(final_fee)?;
+
+    // Serialize and submit transaction
+    let tx_hex = hex::encode(serialize(&tx));
+    debug!("Transaction serialized, submitting to network...");
+
+    // Send raw transaction
+    let txid = rpc.send_raw_transaction(tx_hex)?;
+    debug!("Segwit transaction submitted successfully");
+
+    Ok(txid.to_string())
+}
+
+fn create_second_transaction_segwit(
+    rpc: &RpcClient,
+    first_txid: &str,
+    private_key: &PrivateKey,
+    public_key: &PublicKey,
+    secp: &Secp256k1<bitcoin::secp256k1::All>,
+    network: Network,
+) -> Result<String, TxChainError> {
+    // Get the first transaction details
+    let txid = parse_txid(first_txid)?;
+    let tx_info = rpc.get_raw_transaction_info(&txid, None)?;
+
+    // Get value from the output
+    let value_sats = tx_info.vout[0].value.to_sat();
+    let vout_idx = 0;
+    debug!("First transaction output value: {} satoshis", value_sats);
+
+    // Create input from first transaction
+    let outpoint = OutPoint::new(txid, vout_idx);
+    let txin = TxIn {
+        previous_output: outpoint,
+        script_sig: ScriptBuf::new(),
+        sequence: Sequence::MAX,
+        witness: Witness::new(),
+    };
+
+    // Create output (sending to the same segwit address, minus fees)
+    let dest_address = Address::p2wpkh(&compressed_pubkey(public_key)?, network);
+    let script_code = p2wpkh_script_pubkey(public_key, network)?;
+    let prevout_value = Amount::from_sat(value_sats);
+
+    // Sign with a placeholder fee first so the transaction has its final
+    // witness size and `vsize()` is accurate, then recompute the real fee
+    // from that size and re-sign with the corrected output value.
+    let build_and_sign = |fee: u64| -> Result<Transaction, TxChainError> {
+        let txout = TxOut {
+            value: Amount::from_sat(value_sats - fee),
+            script_pubkey: dest_address.script_pubkey(),
+        };
+        let mut tx = Transaction {
+            version: bitcoin::transaction::Version(2),
+            lock_time: bitcoin::locktime::absolute::LockTime::ZERO,
+            input: vec![txin.clone()],
+            output: vec![txout],
+        };
+
+        // Sign the input using the BIP143 segwit sighash algorithm
+        let sighash = bitcoin::sighash::SighashCache::new(&tx)
+            .p2wpkh_signature_hash(
+                0,
+                &script_code,
+                prevout_value,
+                bitcoin::sighash::EcdsaSighashType::All,
+            )
+            .map_err(|e| TxChainError::Signing(e.to_string()))?;
+
+        let message = bitcoin::secp256k1::Message::from_digest_slice(&sighash[..])
+            .map_err(|e| TxChainError::Signing(e.to_string()))?;
+        let signature = secp.sign_ecdsa(&message, &private_key.inner);
+
+        let mut sig_ser = signature.serialize_der().to_vec();
+        sig_ser.push(bitcoin::sighash::EcdsaSighashType::All.to_u32() as u8);
+
+        let mut witness = Witness::new();
+        witness.push(sig_ser);
+        witness.push(public_key.to_bytes());
+        tx.input[0].witness = witness;
+
+        Ok(tx)
+    };
+
+    debug!("Signing dummy transaction to determine accurate size...");
+    let dummy_tx = build_and_sign(0)?;
+
+    let fee_rate = fee::estimate_fee_rate(rpc, ConfirmationTarget::Normal)?;
+    let final_fee = fee::compute_fee(fee_rate, &dummy_tx);
+    debug!(
+        "Creating segwit transaction with fee: {} satoshis ({:.3} sat/vB)",
+        final_fee, fee_rate
+    );
+
+    debug!("Signing segwit transaction...");
+    let tx = build_and_sign(final_fee)?;
+
+    // Serialize and submit transaction
+    let tx_hex = hex::encode(serialize(&tx));
+    debug!("Transaction serialized, submitting to network...");
+
+    // Send raw transaction
+    let txid = rpc.send_raw_transaction(tx_hex)?;
+    debug!("Segwit transaction submitted successfully");
+
+    Ok(txid.to_string())
+}
+/// Builds a single key-path-spend Taproot (P2TR) output for `keypair`. The
+/// internal key is derived from the keypair's x-only public key, which
+/// `XOnlyPublicKey::from_keypair` already normalizes to even-Y parity; the
+/// stored keypair is tweaked (and negated if needed) to stay consistent with
+/// that choice when the time comes to sign (BIP341).
+fn p2tr_address(
+    secp: &Secp256k1<bitcoin::secp256k1::All>,
+    keypair: &Keypair,
+    network: Network,
+) -> Address {
+    let (internal_key, _parity) = XOnlyPublicKey::from_keypair(keypair);
+    Address::p2tr(secp, internal_key, None, network)
+}
+
+fn create_first_transaction_taproot(
+    rpc: &RpcClient,
+    coinbase_txid: &str,
+    keypair: &Keypair,
+    secp: &Secp256k1<bitcoin::secp256k1::All>,
+    network: Network,
+) -> Result<String, TxChainError> {
+    // Get coinbase transaction details
+    let txid = parse_txid(coinbase_txid)?;
+    let tx_info = rpc.get_raw_transaction_info(&txid, None)?;
+
+    let value_sats = tx_info.vout[0].value.to_sat();
+    let vout_idx = 0;
+    debug!("Coinbase output value: {} satoshis", value_sats);
+
+    let outpoint = OutPoint::new(txid, vout_idx);
+    let txin = TxIn {
+        previous_output: outpoint,
+        script_sig: ScriptBuf::new(),
+        sequence: Sequence::MAX,
+        witness: Witness::new(),
+    };
+
+    let prevout_script = p2tr_address(secp, keypair, network).script_pubkey();
+    let dest_address = p2tr_address(secp, keypair, network);
+    let prevout = TxOut {
+        value: Amount::from_sat(value_sats),
+        script_pubkey: prevout_script,
+    };
+
+    // Sign with a placeholder fee first so the transaction has its final
+    // witness size and `vsize()` is accurate, then recompute the real fee
+    // from that size and re-sign with the corrected output value.
+    let build_and_sign = |fee: u64| -> Result<Transaction, TxChainError> {
+        let txout = TxOut {
+            value: Amount::from_sat(value_sats - fee),
+            script_pubkey: dest_address.script_pubkey(),
+        };
+        let mut tx = Transaction {
+            version: bitcoin::transaction::Version(2),
+            lock_time: bitcoin::locktime::absolute::LockTime::ZERO,
+            input: vec![txin.clone()],
+            output: vec![txout],
+        };
+
+        // Sign the input via the BIP341 key-path spend, which needs the TxOuts of
+        // every input being spent (here, just the one).
+        let sighash = bitcoin::sighash::SighashCache::new(&tx)
+            .taproot_key_spend_signature_hash(
+                0,
+                &Prevouts::All(std::slice::from_ref(&prevout)),
+                TapSighashType::Default,
+            )
+            .map_err(|e| TxChainError::Signing(e.to_string()))?;
+
+        let message = bitcoin::secp256k1::Message::from_digest_slice(&sighash[..])
+            .map_err(|e| TxChainError::Signing(e.to_string()))?;
+        let tweaked_keypair = keypair.tap_tweak(secp, None);
+        let signature = secp.sign_schnorr(&message, &tweaked_keypair.to_keypair());
+
+        // The sighash type is the implicit default, so the witness is just the 64-byte signature
+        let mut witness = Witness::new();
+        witness.push(signature.as_ref());
+        tx.input[0].witness = witness;
+
+        Ok(tx)
+    };
+
+    debug!("Signing dummy transaction to determine accurate size...");
+    let dummy_tx = build_and_sign(0)?;
+
+    let fee_rate = fee::estimate_fee_rate(rpc, ConfirmationTarget::Normal)?;
+    let final_fee = fee::compute_fee(fee_rate, &dummy_tx);
+    debug!(
+        "Creating taproot transaction with fee: {} satoshis ({:.3} sat/vB)",
+        final_fee, fee_rate
+    );
+
+    debug!("Signing taproot transaction...");
+    let tx = build_and_sign(final_fee)?;
+
+    let tx_hex = hex::encode(serialize(&tx));
+    debug!("Transaction serialized, submitting to network...");
+
+    let txid = rpc.send_raw_transaction(tx_hex)?;
+    debug!("Taproot transaction submitted successfully");
+
+    Ok(txid.to_string())
+}
+
+fn create_second_transaction_taproot(
+    rpc: &RpcClient,
+    first_txid: &str,
+    keypair: &Keypair,
+    secp: &Secp256k1<bitcoin::secp256k1::All>,
+    network: Network,
+) -> Result<String, TxChainError> {
+    let txid = parse_txid(first_txid)?;
+    let tx_info = rpc.get_raw_transaction_info(&txid, None)?;
+
+    let value_sats = tx_info.vout[0].value.to_sat();
+    let vout_idx = 0;
+    debug!("First taproot transaction output value: {} satoshis", value_sats);
+
+    let outpoint = OutPoint::new(txid, vout_idx);
+    let txin = TxIn {
+        previous_output: outpoint,
+        script_sig: ScriptBuf::new(),
+        sequence: Sequence::MAX,
+        witness: Witness::new(),
+    };
+
+    let prevout_script = p2tr_address(secp, keypair, network).script_pubkey();
+    let dest_address = p2tr_address(secp, keypair, network);
+    let prevout = TxOut {
+        value: Amount::from_sat(value_sats),
+        script_pubkey: prevout_script,
+    };
+
+    // Sign with a placeholder fee first so the transaction has its final
+    // witness size and `vsize()` is accurate, then recompute the real fee
+    // from that size and re-sign with the corrected output value.
+    let build_and_sign = |fee: u64| -> Result<Transaction, TxChainError> {
+        let txout = TxOut {
+            value: Amount::from_sat(value_sats - fee),
+            script_pubkey: dest_address.script_pubkey(),
+        };
+        let mut tx = Transaction {
+            version: bitcoin::transaction::Version(2),
+            lock_time: bitcoin::locktime::absolute::LockTime::ZERO,
+            input: vec![txin.clone()],
+            output: vec![txout],
+        };
+
+        let sighash = bitcoin::sighash::SighashCache::new(&tx)
+            .taproot_key_spend_signature_hash(
+                0,
+                &Prevouts::All(std::slice::from_ref(&prevout)),
+                TapSighashType::Default,
+            )
+            .map_err(|e| TxChainError::Signing(e.to_string()))?;
+
+        let message = bitcoin::secp256k1::Message::from_digest_slice(&sighash[..])
+            .map_err(|e| TxChainError::Signing(e.to_string()))?;
+        let tweaked_keypair = keypair.tap_tweak(secp, None);
+        let signature = secp.sign_schnorr(&message, &tweaked_keypair.to_keypair());
+
+        let mut witness = Witness::new();
+        witness.push(signature.as_ref());
+        tx.input[0].witness = witness;
+
+        Ok(tx)
+    };
+
+    debug!("Signing dummy transaction to determine accurate size...");
+    let dummy_tx = build_and_sign(0)?;
+
+    let fee_rate = fee::estimate_fee_rate(rpc, ConfirmationTarget::Normal)?;
+    let final_fee = fee::compute_fee(fee_rate, &dummy_tx);
+    debug!(
+        "Creating taproot transaction with fee: {} satoshis ({:.3} sat/vB)",
+        final_fee, fee_rate
+    );
+
+    debug!("Signing taproot transaction...");
+    let tx = build_and_sign(final_fee)?;
+
+    let tx_hex = hex::encode(serialize(&tx));
+    debug!("Transaction serialized, submitting to network...");
+
+    let txid = rpc.send_raw_transaction(tx_hex)?;
+    debug!("Taproot transaction submitted successfully");
+
+    Ok(txid.to_string())
+}
+
+/// Rebuilds and rebroadcasts a `send_amount` transaction at a higher fee
+/// rate, reducing the change output by the fee delta and re-signing from
+/// scratch (BIP125 replacement requires a full re-signature, not just an
+/// edited field). `original_txid`'s inputs must have signaled replaceability,
+/// e.g. via `send_amount`'s `enable_rbf` flag. Fails if the transaction has
+/// already confirmed. The new fee is the estimate for `new_fee_rate`, raised
+/// to one satoshi above the old fee when the estimate is not higher (BIP125
+/// rule 4).
+fn bump_fee_rbf(
+    ctx: &WalletContext,
+    original_txid: &str,
+    from_address: &Address,
+    to_address: &Address,
+    target_amount: Amount,
+    metadata: Option<&[u8]>,
+    new_fee_rate: ConfirmationTarget,
+) -> Result<String, TxChainError> {
+    let txid = parse_txid(original_txid)?;
+    let tx_info = ctx.rpc.get_raw_transaction_info(&txid, None)?;
+
+    if tx_info.confirmations.unwrap_or(0) > 0 {
+        return Err(TxChainError::AlreadyConfirmed {
+            txid: original_txid.to_string(),
+            context: "nothing to replace".to_string(),
+        });
+    }
+
+    // Reuse the exact same inputs, re-signed with a BIP125-signaling sequence.
+    let mut input_total = Amount::from_sat(0);
+    let mut inputs = Vec::with_capacity(tx_info.vin.len());
+    for vin in &tx_info.vin {
+        let prev_txid = vin.txid.ok_or_else(|| TxChainError::Parse {
+            what: "replacement input".to_string(),
+            reason: "coinbase inputs can't be replaced".to_string(),
+        })?;
+        let prev_vout = vin.vout.ok_or_else(|| TxChainError::Parse {
+            what: "replacement input".to_string(),
+            reason: "missing vout index".to_string(),
+        })?;
+        let prev_info = ctx.rpc.get_raw_transaction_info(&prev_txid, None)?;
+        input_total += prev_info.vout[prev_vout as usize].value;
+        inputs.push(TxIn {
+            previous_output: OutPoint::new(prev_txid, prev_vout),
+            script_sig: ScriptBuf::new(),
+            sequence: rbf::RBF_SEQUENCE,
+            witness: Witness::new(),
+        });
+    }
+
+    let old_output_total = tx_info
+        .vout
+        .iter()
+        .fold(Amount::from_sat(0), |acc, v| acc + v.value);
+    let old_fee = (input_total - old_output_total).to_sat();
+    let old_change = input_total - target_amount - Amount::from_sat(old_fee);
+    debug!(
+        "Original transaction paid {} sat over {} vB",
+        old_fee, tx_info.vsize
+    );
+
+    let op_return_output = metadata.map(metadata::build_op_return_output).transpose()?;
+    let script_code = from_address.script_pubkey();
+
+    let build_and_sign = |change: Amount| -> Result<Transaction, TxChainError> {
+        let mut outputs = vec![TxOut {
+            value: target_amount,
+            script_pubkey: to_address.script_pubkey(),
+        }];
+        if change > Amount::from_sat(0) {
+            outputs.push(TxOut {
+                value: change,
+                script_pubkey: from_address.script_pubkey(),
+            });
+        }
+        if let Some(op_return_output) = &op_return_output {
+            outputs.push(op_return_output.clone());
+        }
+
+        let mut tx = Transaction {
+            version: bitcoin::transaction::Version(2),
+            lock_time: bitcoin::locktime::absolute::LockTime::ZERO,
+            input: inputs.clone(),
+            output: outputs,
+        };
+
+        for index in 0..tx.input.len() {
+            let message = legacy_sighash_message(&tx, index, &script_code)?;
+            let signature = ctx.secp.sign_ecdsa(&message, &ctx.private_key.inner);
+
+            let mut sig_ser = signature.serialize_der().to_vec();
+            sig_ser.push(bitcoin::sighash::EcdsaSighashType::All.to_u32() as u8);
+
+            tx.input[index].script_sig = ScriptBuf::builder()
+                .push_slice(push_bytes(&sig_ser)?)
+                .push_key(ctx.public_key)
+                .into_script();
+        }
+
+        Ok(tx)
+    };
+
+    debug!("Signing dummy replacement to determine accurate size...");
+    let dummy_tx = build_and_sign(old_change)?;
+    let new_rate = fee::estimate_fee_rate(ctx.rpc, new_fee_rate)?;
+    let replacement_fee = fee::compute_fee(new_rate, &dummy_tx).max(old_fee + 1);
+    rbf::fee_delta(old_fee, replacement_fee)?;
+    if input_total < target_amount + Amount::from_sat(replacement_fee) {
+        return Err(TxChainError::InsufficientFunds {
+            needed: (target_amount + Amount::from_sat(replacement_fee)).to_string(),
+            available: input_total.to_string(),
+        });
+    }
+    let new_change = input_total - target_amount - Amount::from_sat(replacement_fee);
+    debug!(
+        "Replacement transaction paying {} sat ({:.3} sat/vB), change: {}",
+        replacement_fee, new_rate, new_change
+    );
+
+    let tx = build_and_sign(new_change)?;
+    let tx_hex = hex::encode(serialize(&tx));
+    debug!("Replacement transaction serialized, submitting to network...");
+
+    let txid = ctx.rpc.send_raw_transaction(tx_hex)?;
+    debug!("RBF replacement submitted successfully");
+
+    Ok(txid.to_string())
+}
+
+/// The standard minimum non-dust value for a P2PKH output; a CPFP child fee
+/// is never allowed to eat into the parent output below this.
+const DUST_LIMIT_SATS: u64 = 546;
+
+/// Spends an unconfirmed output of `parent_txid` back to `owner_address`,
+/// sized so the combined parent+child package fee rate reaches `target_rate`
+/// even though the parent's own fee is too low to confirm quickly on its own
+/// (Child Pays For Parent). Fails if the parent has already confirmed, since
+/// CPFP is then moot.
+fn cpfp_bump(
+    ctx: &WalletContext,
+    parent_txid: &str,
+    parent_vout: u32,
+    owner_address: &Address,
+    target_rate: ConfirmationTarget,
+) -> Result<String, TxChainError> {
+    let txid = parse_txid(parent_txid)?;
+    let parent_info = ctx.rpc.get_raw_transaction_info(&txid, None)?;
+
+    if parent_info.confirmations.unwrap_or(0) > 0 {
+        return Err(TxChainError::AlreadyConfirmed {
+            txid: parent_txid.to_string(),
+            context: "CPFP is unnecessary".to_string(),
+        });
+    }
+
+    let parent_value_sats = parent_info.vout[parent_vout as usize].value.to_sat();
+
+    let mut parent_input_total = Amount::from_sat(0);
+    for vin in &parent_info.vin {
+        let prev_txid = vin.txid.ok_or_else(|| TxChainError::Parse {
+            what: "parent input".to_string(),
+            reason: "coinbase parents aren't supported".to_string(),
+        })?;
+        let prev_vout = vin.vout.ok_or_else(|| TxChainError::Parse {
+            what: "parent input".to_string(),
+            reason: "missing vout index".to_string(),
+        })?;
+        let prev_info = ctx.rpc.get_raw_transaction_info(&prev_txid, None)?;
+        parent_input_total += prev_info.vout[prev_vout as usize].value;
+    }
+    let parent_output_total = parent_info
+        .vout
+        .iter()
+        .fold(Amount::from_sat(0), |acc, v| acc + v.value);
+    let parent_fee = (parent_input_total - parent_output_total).to_sat();
+    let parent_vsize = parent_info.vsize as u64;
+
+    let outpoint = OutPoint::new(txid, parent_vout);
+    let txin = TxIn {
+        previous_output: outpoint,
+        script_sig: ScriptBuf::new(),
+        sequence: rbf::RBF_SEQUENCE,
+        witness: Witness::new(),
+    };
+    let script_code = owner_address.script_pubkey();
+
+    let build_and_sign = |fee: u64| -> Result<Transaction, TxChainError> {
+        let txout = TxOut {
+            value: Amount::from_sat(parent_value_sats - fee),
+            script_pubkey: owner_address.script_pubkey(),
+        };
+        let mut tx = Transaction {
+            version: bitcoin::transaction::Version(2),
+            lock_time: bitcoin::locktime::absolute::LockTime::ZERO,
+            input: vec![txin.clone()],
+            output: vec![txout],
+        };
+
+        let message = legacy_sighash_message(&tx, 0, &script_code)?;
+        let signature = ctx.secp.sign_ecdsa(&message, &ctx.private_key.inner);
+
+        let mut sig_ser = signature.serialize_der().to_vec();
+        sig_ser.push(bitcoin::sighash::EcdsaSighashType::All.to_u32() as u8);
+
+        tx.input[0].script_sig = ScriptBuf::builder()
+            .push_slice(push_bytes(&sig_ser)?)
+            .push_key(ctx.public_key)
+            .into_script();
+
+        Ok(tx)
+    };
+
+    debug!("Signing dummy CPFP child to determine accurate size...");
+    let dummy_tx = build_and_sign(0)?;
+    let child_vsize = dummy_tx.vsize() as u64;
+
+    let target_rate_sat_per_vb = fee::estimate_fee_rate(ctx.rpc, target_rate)?;
+    let child_fee = rbf::cpfp_child_fee(parent_fee, parent_vsize, child_vsize, target_rate_sat_per_vb);
+
+    // cpfp_child_fee only sizes the fee to reach the target package rate; it
+    // doesn't know the parent output's value, so cap it here and fail closed
+    // rather than let the child output underflow.
+    let max_child_fee = parent_value_sats.saturating_sub(DUST_LIMIT_SATS);
+    if child_fee > max_child_fee {
+        return Err(TxChainError::InsufficientFunds {
+            needed: Amount::from_sat(child_fee).to_string(),
+            available: Amount::from_sat(parent_value_sats).to_string(),
+        });
+    }
+
+    debug!(
+        "Parent pays {} sat over {} vB; child adds {} sat over {} vB to reach {:.3} sat/vB combined",
+        parent_fee, parent_vsize, child_fee, child_vsize, target_rate_sat_per_vb
+    );
+
+    let tx = build_and_sign(child_fee)?;
+    let tx_hex = hex::encode(serialize(&tx));
+    debug!("CPFP child serialized, submitting to network...");
+
+    let txid = ctx.rpc.send_raw_transaction(tx_hex)?;
+    debug!("CPFP child transaction submitted successfully");
+
+    Ok(txid.to_string())
+}
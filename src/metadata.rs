@@ -0,0 +1,38 @@
+use crate::error::TxChainError;
+use bitcoin::script::PushBytesBuf;
+use bitcoin::{Amount, ScriptBuf, TxOut};
+
+/// A fixed prefix identifying transactions produced by this demo, so a
+/// watcher can filter blocks for it without parsing the rest of the payload.
+pub const APP_PREFIX: &[u8] = b"BTCX";
+
+/// The standardness limit for OP_RETURN payloads enforced by Bitcoin Core's
+/// default relay policy.
+pub const MAX_OP_RETURN_PAYLOAD_BYTES: usize = 80;
+
+/// Builds an `OP_RETURN <APP_PREFIX><payload>` output carrying zero value.
+/// Rejects payloads that would push the combined data past the 80-byte
+/// standardness limit.
+pub fn build_op_return_output(payload: &[u8]) -> Result<TxOut, TxChainError> {
+    let mut data = Vec::with_capacity(APP_PREFIX.len() + payload.len());
+    data.extend_from_slice(APP_PREFIX);
+    data.extend_from_slice(payload);
+
+    if data.len() > MAX_OP_RETURN_PAYLOAD_BYTES {
+        return Err(TxChainError::Encoding(format!(
+            "OP_RETURN payload of {} bytes (prefix + data) exceeds the {}-byte standardness limit",
+            data.len(),
+            MAX_OP_RETURN_PAYLOAD_BYTES
+        )));
+    }
+
+    let mut push_bytes = PushBytesBuf::with_capacity(data.len());
+    push_bytes
+        .extend_from_slice(&data)
+        .map_err(|e| TxChainError::Encoding(e.to_string()))?;
+
+    Ok(TxOut {
+        value: Amount::from_sat(0),
+        script_pubkey: ScriptBuf::new_op_return(push_bytes),
+    })
+}
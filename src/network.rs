@@ -0,0 +1,78 @@
+use crate::error::TxChainError;
+use bitcoin::Network;
+use bitcoincore_rpc::{Auth, Client as RpcClient, RpcApi};
+use log::{debug, info};
+use std::path::PathBuf;
+
+/// Subdirectories (relative to the default bitcoind data directory) that hold
+/// a `.cookie` file for each network, checked in the order a local
+/// development node is most likely to be running. The empty string is
+/// mainnet, whose cookie lives directly under the data directory.
+const COOKIE_SUBDIRS: [&str; 4] = ["regtest", "signet", "testnet3", ""];
+
+/// Looks for a `.cookie` file under the default bitcoind data directory
+/// (`~/.bitcoin`, or `$BITCOIN_DATADIR` if set) and, if one exists, returns
+/// auth that reads it lazily on each RPC call. Falls back to the node's
+/// default `rpcuser`/`rpcpassword` (`alice`/`password`, matching the
+/// regtest node this demo expects) when no cookie file is found, since a
+/// node with auth disabled or pre-shared credentials has nothing to detect.
+pub fn detect_rpc_auth() -> Auth {
+    let data_dir = std::env::var("BITCOIN_DATADIR")
+        .map(PathBuf::from)
+        .or_else(|_| std::env::var("HOME").map(|home| PathBuf::from(home).join(".bitcoin")));
+
+    let Ok(data_dir) = data_dir else {
+        debug!("Could not determine a bitcoind data directory; using default credentials");
+        return Auth::UserPass("alice".to_string(), "password".to_string());
+    };
+
+    for subdir in COOKIE_SUBDIRS {
+        let cookie_path = data_dir.join(subdir).join(".cookie");
+        if cookie_path.is_file() {
+            info!("Using cookie-file auth from {}", cookie_path.display());
+            return Auth::CookieFile(cookie_path);
+        }
+    }
+
+    debug!(
+        "No cookie file found under {}; falling back to default credentials",
+        data_dir.display()
+    );
+    Auth::UserPass("alice".to_string(), "password".to_string())
+}
+
+/// Queries the node for which chain it's running and maps that to the
+/// matching `bitcoin::Network`, so callers don't have to hardcode one.
+/// Fails closed on an unrecognized chain rather than guessing regtest, since
+/// `require_generation_allowed` would otherwise treat an unknown future chain
+/// as safe to mine on.
+pub fn detect_network(rpc: &RpcClient) -> Result<Network, TxChainError> {
+    let chain = rpc.get_blockchain_info()?.chain;
+
+    let network = match chain.to_string().as_str() {
+        "main" => Network::Bitcoin,
+        "test" => Network::Testnet,
+        "regtest" => Network::Regtest,
+        "signet" => Network::Signet,
+        other => {
+            return Err(TxChainError::Parse {
+                what: "node chain".to_string(),
+                reason: format!("unrecognized chain '{}' reported by node", other),
+            });
+        }
+    };
+
+    info!("Detected network: {:?} (node chain: {})", network, chain);
+    Ok(network)
+}
+
+/// Guards destructive block-generation calls (`generatetoaddress` and
+/// friends) so they only ever run against regtest/signet, never mainnet.
+pub fn require_generation_allowed(network: Network) {
+    if !matches!(network, Network::Regtest | Network::Signet) {
+        panic!(
+            "Refusing to generate blocks on {:?}: block generation is only supported on regtest/signet",
+            network
+        );
+    }
+}
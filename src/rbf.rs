@@ -0,0 +1,35 @@
+use crate::error::TxChainError;
+use bitcoin::Sequence;
+
+/// The sequence value that signals BIP125 opt-in replaceability with no
+/// relative locktime. Any input set to this (or lower) sequence tells the
+/// mempool the transaction may be replaced by one paying a higher fee.
+pub const RBF_SEQUENCE: Sequence = Sequence::ENABLE_RBF_NO_LOCKTIME;
+
+/// Checks a proposed replacement fee against BIP125 rule 4 (the replacement
+/// must pay a strictly higher absolute fee than the transaction it evicts)
+/// and returns the delta to be cut from the change output.
+pub fn fee_delta(old_fee: u64, new_fee: u64) -> Result<u64, TxChainError> {
+    if new_fee <= old_fee {
+        return Err(TxChainError::Signing(format!(
+            "replacement fee {} sat must exceed the original fee {} sat (BIP125 rule 4)",
+            new_fee, old_fee
+        )));
+    }
+    Ok(new_fee - old_fee)
+}
+
+/// Computes the child fee, in satoshis, needed for a CPFP package to reach
+/// `target_rate_sat_per_vb` given the parent's already-paid fee/vsize and the
+/// child's own vsize. Saturates at zero if the parent alone already clears
+/// the target (i.e. CPFP isn't actually needed).
+pub fn cpfp_child_fee(
+    parent_fee_sats: u64,
+    parent_vsize: u64,
+    child_vsize: u64,
+    target_rate_sat_per_vb: f64,
+) -> u64 {
+    let required_package_fee =
+        (target_rate_sat_per_vb * (parent_vsize + child_vsize) as f64).ceil() as u64;
+    required_package_fee.saturating_sub(parent_fee_sats)
+}
@@ -0,0 +1,158 @@
+use crate::error::TxChainError;
+use bitcoin::{Address, Amount, OutPoint};
+use bitcoincore_rpc::{Client as RpcClient, RpcApi};
+use log::debug;
+
+/// Minimum confirmations required before a coinbase output can be spent.
+const COINBASE_MATURITY: u64 = 100;
+/// Minimum confirmations required for an ordinary (non-coinbase) output.
+const MIN_CONFIRMATIONS: u64 = 1;
+
+/// A spendable output discovered via `listunspent`.
+#[derive(Debug, Clone)]
+pub struct Utxo {
+    pub outpoint: OutPoint,
+    pub value: Amount,
+    pub confirmations: u64,
+    pub is_coinbase: bool,
+}
+
+/// Coin-selection strategy to apply when choosing which UTXOs fund a target amount.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SelectionStrategy {
+    /// Sorts by descending value and takes from the top until the target is covered.
+    LargestFirst,
+    /// Looks for the subset whose value exceeds the target by the least, and by
+    /// at most 1000 sat, falling back to largest-first when no such subset
+    /// exists. The excess is returned as the selection's `change` like any
+    /// other leftover; this strategy does not by itself suppress a change output.
+    BranchAndBound,
+}
+
+/// The chosen inputs plus the amount left over to send back as change.
+pub struct Selection {
+    pub utxos: Vec<Utxo>,
+    pub change: Amount,
+}
+
+/// Enumerates spendable outputs belonging to `address`, filtering out coinbase
+/// outputs that haven't reached maturity.
+pub fn list_spendable_utxos(
+    rpc: &RpcClient,
+    address: &Address,
+) -> Result<Vec<Utxo>, TxChainError> {
+    let entries = rpc.list_unspent(Some(1), None, Some(&[address]), None, None)?;
+
+    let mut utxos = Vec::with_capacity(entries.len());
+    for entry in entries {
+        let is_coinbase = rpc
+            .get_raw_transaction_info(&entry.txid, None)?
+            .vin
+            .iter()
+            .any(|vin| vin.coinbase.is_some());
+        let required_confirmations = if is_coinbase {
+            COINBASE_MATURITY
+        } else {
+            MIN_CONFIRMATIONS
+        };
+
+        if (entry.confirmations as u64) < required_confirmations {
+            debug!(
+                "Skipping immature utxo {}:{} ({} confirmations, needs {})",
+                entry.txid, entry.vout, entry.confirmations, required_confirmations
+            );
+            continue;
+        }
+
+        utxos.push(Utxo {
+            outpoint: OutPoint::new(entry.txid, entry.vout),
+            value: entry.amount,
+            confirmations: entry.confirmations as u64,
+            is_coinbase,
+        });
+    }
+
+    Ok(utxos)
+}
+
+/// Selects a set of UTXOs covering `target_plus_fee` using `strategy`.
+/// Returns `None` if the available UTXOs can't cover the target.
+pub fn select_utxos(
+    utxos: &[Utxo],
+    target_plus_fee: Amount,
+    strategy: SelectionStrategy,
+) -> Option<Selection> {
+    match strategy {
+        SelectionStrategy::LargestFirst => select_largest_first(utxos, target_plus_fee),
+        SelectionStrategy::BranchAndBound => select_branch_and_bound(utxos, target_plus_fee)
+            .or_else(|| select_largest_first(utxos, target_plus_fee)),
+    }
+}
+
+fn select_largest_first(utxos: &[Utxo], target_plus_fee: Amount) -> Option<Selection> {
+    let mut sorted: Vec<Utxo> = utxos.to_vec();
+    sorted.sort_by_key(|utxo| std::cmp::Reverse(utxo.value));
+
+    let mut chosen = Vec::new();
+    let mut total = Amount::from_sat(0);
+    for utxo in sorted {
+        if total >= target_plus_fee {
+            break;
+        }
+        total += utxo.value;
+        chosen.push(utxo);
+    }
+
+    if total < target_plus_fee {
+        return None;
+    }
+
+    Some(Selection {
+        utxos: chosen,
+        change: total - target_plus_fee,
+    })
+}
+
+/// A bounded brute-force search over subsets, looking for the combination whose
+/// value exceeds the target by the least, and by no more than
+/// `NO_CHANGE_THRESHOLD`. That excess is returned as the selection's `change`.
+/// Only attempted for small UTXO sets, since it's exponential.
+fn select_branch_and_bound(utxos: &[Utxo], target_plus_fee: Amount) -> Option<Selection> {
+    const NO_CHANGE_THRESHOLD: Amount = Amount::from_sat(1000);
+    const MAX_UTXOS_TO_TRY: usize = 20;
+
+    if utxos.is_empty() || utxos.len() > MAX_UTXOS_TO_TRY {
+        return None;
+    }
+
+    let mut best: Option<(Vec<Utxo>, Amount)> = None;
+    let n = utxos.len();
+    for mask in 1u32..(1u32 << n) {
+        let mut total = Amount::from_sat(0);
+        let mut subset = Vec::new();
+        for (i, utxo) in utxos.iter().enumerate() {
+            if mask & (1 << i) != 0 {
+                total += utxo.value;
+                subset.push(utxo.clone());
+            }
+        }
+
+        if total < target_plus_fee {
+            continue;
+        }
+        let excess = total - target_plus_fee;
+        if excess > NO_CHANGE_THRESHOLD {
+            continue;
+        }
+
+        let is_better = match &best {
+            Some((_, best_excess)) => excess < *best_excess,
+            None => true,
+        };
+        if is_better {
+            best = Some((subset, excess));
+        }
+    }
+
+    best.map(|(utxos, change)| Selection { utxos, change })
+}